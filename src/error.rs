@@ -0,0 +1,61 @@
+use std::fmt;
+
+use actix_multipart::MultipartError;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+
+/// Crate-wide error type returned by the HTTP handlers in place of panicking.
+///
+/// I/O failures are classified by `std::io::ErrorKind` into the closest matching HTTP status,
+/// so that things like a missing file or a full disk surface as a normal response instead of
+/// taking down the worker thread.
+#[derive(Debug)]
+pub enum AppError {
+    NotFound,
+    StorageFull,
+    Io(std::io::Error),
+    Multipart(MultipartError),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound => write!(f, "file not found"),
+            AppError::StorageFull => write!(f, "no space left on device"),
+            AppError::Io(err) => write!(f, "I/O error: {}", err),
+            AppError::Multipart(err) => write!(f, "invalid multipart payload: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound,
+            std::io::ErrorKind::StorageFull => AppError::StorageFull,
+            _ => AppError::Io(err),
+        }
+    }
+}
+
+impl From<MultipartError> for AppError {
+    fn from(err: MultipartError) -> Self {
+        AppError::Multipart(err)
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::StorageFull => StatusCode::INSUFFICIENT_STORAGE,
+            AppError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Multipart(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).body(self.to_string())
+    }
+}
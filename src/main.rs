@@ -1,4 +1,7 @@
+mod error;
+
 use actix_multipart::Multipart;
+use error::AppError;
 use std::ops::Deref;
 use std::path::PathBuf;
 use actix_web::{delete, get, post, web, App, HttpResponse, HttpServer, Responder};
@@ -9,9 +12,48 @@ use std::fs;
 use std::path::Path;
 use tokio_util::io::ReaderStream;
 use clap::{Command, arg};
-use rustls::{ServerConfig, Certificate};
+use serde::Serialize;
+use rustls::{ServerConfig, Certificate, RootCertStore};
+use rustls::server::{AllowAnyAuthenticatedClient, AllowAnyAnonymousOrAuthenticatedClient};
 use rustls_pemfile::{certs, rsa_private_keys, pkcs8_private_keys, ec_private_keys};
 
+/// Metadata about a single entry returned by the [`list_directory`] endpoint.
+#[derive(Serialize)]
+struct DirEntryInfo {
+    name: String,
+    size: u64,
+    is_dir: bool,
+    /// Last modified time as a Unix timestamp (seconds since the epoch).
+    modified: u64,
+}
+
+/// Resolves a user-supplied, slash-separated path against the serving root, sanitizing every
+/// path component individually so that `..` segments and other traversal tricks can't escape it.
+///
+/// The result is canonicalized and checked to still be a prefix of `root` (which is expected to
+/// already be canonical), so symlinks inside the served tree can't be used to escape it either.
+/// Returns `None` if the path doesn't resolve to anything under `root`.
+fn resolve_under_root(root: &Path, raw_path: &str) -> Option<PathBuf> {
+    let mut candidate = root.to_path_buf();
+    for segment in raw_path.split('/') {
+        if segment.is_empty() || segment == "." {
+            continue;
+        }
+        let sanitized = sanitize(segment);
+        if sanitized.is_empty() {
+            continue;
+        }
+        candidate.push(sanitized);
+    }
+
+    let canonical = candidate.canonicalize().ok()?;
+    if canonical.starts_with(root) {
+        Some(canonical)
+    } else {
+        None
+    }
+}
+
 /// Handles file uploads to the server.
 ///
 /// This function uses the `Multipart` request payload to process the uploaded file. It goes through each part
@@ -27,6 +69,7 @@ use rustls_pemfile::{certs, rsa_private_keys, pkcs8_private_keys, ec_private_key
 /// # Arguments
 ///
 /// * `payload` - A mutable reference to a `Multipart` payload, which represents the uploaded file data.
+/// * `root` - The directory the server is configured to serve, set via `--root`.
 ///
 /// # Returns
 ///
@@ -34,25 +77,30 @@ use rustls_pemfile::{certs, rsa_private_keys, pkcs8_private_keys, ec_private_key
 /// * `Ok` with a success message as the body if the file was successfully uploaded.
 /// * `BadRequest` if the filename is invalid or empty.
 /// * `Conflict` if a file with the same name already exists on the server.
+///
+/// # Errors
+///
+/// Returns `AppError` if the file can't be created or written to, e.g. a permissions issue
+/// or the disk running out of space.
 #[post("/upload")]
-async fn upload(mut payload: Multipart) -> impl Responder {
-    while let Ok(Some(mut field)) = payload.try_next().await {
+async fn upload(mut payload: Multipart, root: web::Data<PathBuf>) -> Result<HttpResponse, AppError> {
+    while let Some(mut field) = payload.try_next().await? {
         let content_disposition = field.content_disposition();
         let filename = sanitize(content_disposition.get_filename().unwrap_or_default());
         if filename.is_empty() {
-            return HttpResponse::BadRequest().body("Invalid filename");
+            return Ok(HttpResponse::BadRequest().body("Invalid filename"));
         }
-        let filepath = format!("./{}", filename);
-        if Path::new(&filepath).exists() {
-            return HttpResponse::Conflict().body("File already exists");
+        let filepath = root.join(&filename);
+        if filepath.exists() {
+            return Ok(HttpResponse::Conflict().body("File already exists"));
         }
-        let mut f = File::create(&filepath).await.unwrap();
+        let mut f = File::create(&filepath).await?;
         while let Some(chunk) = field.next().await {
-            let data = chunk.unwrap();
-            f.write_all(&data).await.unwrap();
+            let data = chunk?;
+            f.write_all(&data).await?;
         }
     }
-    HttpResponse::Ok().body("File uploaded successfully")
+    Ok(HttpResponse::Ok().body("File uploaded successfully"))
 }
 
 /// Handles download requests for files on the server by checking if the 
@@ -62,51 +110,186 @@ async fn upload(mut payload: Multipart) -> impl Responder {
 /// # Arguments
 ///
 /// * `filename` - A `web::Path<String>` representing the filename to download.
+/// * `root` - The directory the server is configured to serve, set via `--root`.
 ///
 /// # Returns
 ///
-/// An `HttpResponse` which can be `Ok` with the file's content as the body 
+/// An `HttpResponse` which can be `Ok` with the file's content as the body
 /// or `NotFound` if the file doesn't exist.
+///
+/// # Errors
+///
+/// Returns `AppError` if the file exists but can't be read, e.g. a permissions issue.
 #[get("/download/{filename}")]
-async fn download(filename: web::Path<String>) -> impl Responder {
+async fn download(filename: web::Path<String>, root: web::Data<PathBuf>) -> Result<HttpResponse, AppError> {
     let filename = sanitize(filename.into_inner());
-    let filepath = format!("./{}", filename);
 
-    if Path::new(&filepath).exists() {
-        let data = fs::read(filepath).unwrap();
-        HttpResponse::Ok().body(data)
+    match resolve_under_root(&root, &filename) {
+        Some(filepath) => {
+            let data = fs::read(filepath)?;
+            Ok(HttpResponse::Ok().body(data))
+        }
+        None => Ok(HttpResponse::NotFound().body("File not found")),
+    }
+}
+
+/// Parses an HTTP `Range` header value of the form `bytes=start-end`, `bytes=start-` or
+/// `bytes=-suffix_len` into an inclusive `(start, end)` byte range, clamped to `file_len`.
+///
+/// Returns `None` if the header is malformed or the range is unsatisfiable for a file of
+/// `file_len` bytes (e.g. `start` at or beyond the end of the file).
+fn parse_range(range_header: &str, file_len: u64) -> Option<(u64, u64)> {
+    let range = range_header.strip_prefix("bytes=")?;
+    let (start_str, end_str) = range.split_once('-')?;
+
+    if start_str.is_empty() {
+        // Suffix range, e.g. "bytes=-500" means the last 500 bytes of the file.
+        let suffix_len: u64 = end_str.parse().ok()?;
+        if suffix_len == 0 || file_len == 0 {
+            return None;
+        }
+        let start = file_len.saturating_sub(suffix_len);
+        return Some((start, file_len - 1));
+    }
+
+    let start: u64 = start_str.parse().ok()?;
+    if start >= file_len {
+        return None;
+    }
+    let end = if end_str.is_empty() {
+        file_len - 1
     } else {
-        HttpResponse::NotFound().body("File not found")
+        end_str.parse::<u64>().ok()?.min(file_len - 1)
+    };
+    if start > end {
+        return None;
     }
+    Some((start, end))
 }
 
-/// Handles download requests for files on the server by checking if the 
-/// requested file exists, and if it does, returns the file's content in chunks.
-/// This is efficient for large files as it streams the file in chunks rather than reading the 
-/// entire file into memory.
+/// Handles download requests for files on the server by checking if the
+/// requested file exists, and if it does, streams its content in chunks rather than reading the
+/// entire file into memory, which is efficient for large files.
+///
+/// Supports HTTP `Range` requests (`bytes=start-end`, open-ended `start-`, and suffix `-n`
+/// forms) so that interrupted downloads can resume and media players can seek, responding
+/// `206 Partial Content` with the matching `Content-Range` header, or `416 Range Not Satisfiable`
+/// if the requested range doesn't fit within the file.
 ///
 /// # Arguments
 ///
+/// * `req` - The incoming `HttpRequest`, used to read the `Range` header.
 /// * `path` - A `web::Path<String>` representing the path to the file to download.
+/// * `root` - The directory the server is configured to serve, set via `--root`.
 ///
 /// # Returns
 ///
-/// An `HttpResponse` which can be `Ok` with a `Stream` of the file's content as the body,
-/// `InternalServerError` if there was a problem reading the file,
-/// or `NotFound` if the file doesn't exist.
+/// An `HttpResponse` which can be `Ok` or `PartialContent` with a `Stream` of the file's content
+/// as the body, `RangeNotSatisfiable` if the `Range` header can't be satisfied,
+/// `InternalServerError` if there was a problem reading the file, or `NotFound` if the file
+/// doesn't exist.
 #[get("/download-chunked/{filename:.*}")]
-async fn chunked_download(path: web::Path<String>) -> impl Responder {
-    let filename = sanitize(path.into_inner());
-    let file_path = PathBuf::from("./").join(filename);
-
-    if file_path.exists() {
-        match File::open(&file_path).await {
-            Ok(file) => HttpResponse::Ok().streaming(ReaderStream::new(file)),
-            Err(_) => HttpResponse::InternalServerError().body("Could not read file"),
-        }
-    } else {
-        HttpResponse::NotFound().body("File not found")
+async fn chunked_download(req: actix_web::HttpRequest, path: web::Path<String>, root: web::Data<PathBuf>) -> impl Responder {
+    let file_path = match resolve_under_root(&root, &path.into_inner()) {
+        Some(p) => p,
+        None => return HttpResponse::NotFound().body("File not found"),
+    };
+
+    if !file_path.is_file() {
+        return HttpResponse::NotFound().body("File not found");
+    }
+
+    let mut file = match File::open(&file_path).await {
+        Ok(file) => file,
+        Err(_) => return HttpResponse::InternalServerError().body("Could not read file"),
+    };
+    let file_len = match file.metadata().await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return HttpResponse::InternalServerError().body("Could not read file"),
+    };
+
+    let range_header = req
+        .headers()
+        .get(actix_web::http::header::RANGE)
+        .and_then(|value| value.to_str().ok());
+
+    let Some(range_header) = range_header else {
+        return HttpResponse::Ok()
+            .insert_header((actix_web::http::header::ACCEPT_RANGES, "bytes"))
+            .streaming(ReaderStream::new(file));
+    };
+
+    let Some((start, end)) = parse_range(range_header, file_len) else {
+        return HttpResponse::build(actix_web::http::StatusCode::RANGE_NOT_SATISFIABLE)
+            .insert_header((actix_web::http::header::CONTENT_RANGE, format!("bytes */{}", file_len)))
+            .finish();
+    };
+
+    if io::AsyncSeekExt::seek(&mut file, io::SeekFrom::Start(start)).await.is_err() {
+        return HttpResponse::InternalServerError().body("Could not read file");
+    }
+
+    let chunk_len = end - start + 1;
+    let limited = io::AsyncReadExt::take(file, chunk_len);
+
+    HttpResponse::PartialContent()
+        .insert_header((actix_web::http::header::ACCEPT_RANGES, "bytes"))
+        .insert_header((actix_web::http::header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, file_len)))
+        .streaming(ReaderStream::new(limited))
+}
+
+/// Lists the contents of a directory under the serving root as JSON.
+///
+/// This mirrors `chunked_download`'s path handling: the requested path is resolved and
+/// canonicalized against `root` so that it cannot be used to browse outside the served tree.
+///
+/// # Arguments
+///
+/// * `path` - A `web::Path<String>` representing the directory to list, relative to `root`.
+/// * `root` - The directory the server is configured to serve, set via `--root`.
+///
+/// # Returns
+///
+/// An `HttpResponse` which can be `Ok` with a JSON array of entries (name, size, is_dir, modified),
+/// `NotFound` if the directory doesn't exist, or `InternalServerError` if it could not be read.
+#[get("/list/{path:.*}")]
+async fn list_directory(path: web::Path<String>, root: web::Data<PathBuf>) -> impl Responder {
+    let dir_path = match resolve_under_root(&root, &path.into_inner()) {
+        Some(p) => p,
+        None => return HttpResponse::NotFound().body("Directory not found"),
+    };
+
+    if !dir_path.is_dir() {
+        return HttpResponse::NotFound().body("Directory not found");
+    }
+
+    let mut read_dir = match tokio::fs::read_dir(&dir_path).await {
+        Ok(read_dir) => read_dir,
+        Err(_) => return HttpResponse::InternalServerError().body("Could not read directory"),
+    };
+
+    let mut entries = Vec::new();
+    while let Ok(Some(entry)) = read_dir.next_entry().await {
+        let metadata = match entry.metadata().await {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        entries.push(DirEntryInfo {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            size: metadata.len(),
+            is_dir: metadata.is_dir(),
+            modified,
+        });
     }
+
+    HttpResponse::Ok().json(entries)
 }
 
 /// Handles delete requests for files on the server.
@@ -118,22 +301,28 @@ async fn chunked_download(path: web::Path<String>) -> impl Responder {
 /// # Arguments
 ///
 /// * `filename` - A `web::Path<String>` representing the filename to delete.
+/// * `root` - The directory the server is configured to serve, set via `--root`.
 ///
 /// # Returns
 ///
 /// An `HttpResponse` which can be:
 /// * `Ok` with a success message as the body if the file was successfully deleted.
 /// * `NotFound` if the file does not exist on the server.
+///
+/// # Errors
+///
+/// Returns `AppError` if the file exists but can't be removed, e.g. a permissions issue or a
+/// concurrent request deleting it first.
 #[delete("/{filename}")]
-async fn delete(filename: web::Path<String>) -> impl Responder {
+async fn delete(filename: web::Path<String>, root: web::Data<PathBuf>) -> Result<HttpResponse, AppError> {
     let filename = sanitize(filename.into_inner());
-    let filepath = format!("./{}", filename);
+    let filepath = root.join(&filename);
 
-    if Path::new(&filepath).exists() {
-        fs::remove_file(filepath).unwrap();
-        HttpResponse::Ok().body("File deleted successfully")
+    if filepath.exists() {
+        fs::remove_file(filepath)?;
+        Ok(HttpResponse::Ok().body("File deleted successfully"))
     } else {
-        HttpResponse::NotFound().body("File not found")
+        Ok(HttpResponse::NotFound().body("File not found"))
     }
 }
 
@@ -146,13 +335,21 @@ async fn delete(filename: web::Path<String>) -> impl Responder {
 /// * `--port [PORT]`: The port to listen on. Defaults to 3000.
 /// * `--tls-cert [CERT]`: The path to the TLS certificate file. Optional.
 /// * `--tls-key [KEY]`: The path to the TLS key file. Optional.
+/// * `--client-ca [CA]`: Path to a PEM bundle of CA certificates trusted to authenticate clients. Optional.
+/// * `--require-client-cert`: Reject clients that do not present a certificate trusted by `--client-ca`.
+/// * `--keylog`: Log TLS session secrets to the file named by `SSLKEYLOGFILE` for decrypting captured
+///   traffic. Setting `SSLKEYLOGFILE` alone also enables it; without `SSLKEYLOGFILE` nothing is logged.
+/// * `--root [DIR]`: Directory to serve files from. Defaults to the current directory.
 ///
 /// If both `--tls-cert` and `--tls-key` are provided, the server will use HTTPS. Otherwise, it will use HTTP.
+/// If `--client-ca` is also provided, the server additionally verifies client certificates against that
+/// CA bundle, enforcing presentation of a valid certificate when `--require-client-cert` is set.
 ///
 /// The server provides the following services:
 /// * `upload`: Upload a file to the server.
 /// * `download`: Download a file from the server.
 /// * `chunked_download`: Download a file from the server in chunks.
+/// * `list_directory`: List the contents of a directory under `--root` as JSON.
 /// * `delete`: Delete a file from the server.
 ///
 /// The server can be shut down by pressing ENTER.
@@ -186,12 +383,20 @@ async fn main() -> std::io::Result<()> {
     .arg(arg!(--port [PORT] "Port to listen on").default_value("3000"))
     .arg(arg!(--"tls-cert" [CERT] "Path to the TLS certificate file"))
     .arg(arg!(--"tls-key" [KEY] "Path to the TLS key file"))
+    .arg(arg!(--"client-ca" [CA] "Path to a PEM bundle of CA certificates trusted to authenticate clients"))
+    .arg(arg!(--"require-client-cert" "Reject TLS handshakes from clients that do not present a certificate signed by --client-ca"))
+    .arg(arg!(--keylog "Log TLS session secrets to the file named by SSLKEYLOGFILE (NSS key-log format) for debugging with Wireshark"))
+    .arg(arg!(--root [DIR] "Directory to serve files from").default_value("."))
     .get_matches();
 
     // Get the port from the command line arguments
     let port = matches.get_one::<String>("port").unwrap().as_str();
     let bind_address = format!("0.0.0.0:{}", port);
 
+    // Canonicalize the serving root once up front so every handler can resolve
+    // user-supplied paths against it and reject anything that escapes it.
+    let root = std::fs::canonicalize(matches.get_one::<String>("root").unwrap())?;
+
     // Create a one-shot channel for shutting down the server
     let (tx, rx) = tokio::sync::oneshot::channel();
 
@@ -204,11 +409,13 @@ async fn main() -> std::io::Result<()> {
     });
 
     // Create a new HTTP server
-    let server = HttpServer::new(|| {
+    let server = HttpServer::new(move || {
         App::new()
+            .app_data(web::Data::new(root.clone()))
             .service(upload)
             .service(download)
             .service(chunked_download)
+            .service(list_directory)
             .service(delete)
     });
 
@@ -255,12 +462,57 @@ async fn main() -> std::io::Result<()> {
             return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid certificate or key"));
         }
     
+        // `--require-client-cert` only makes sense alongside `--client-ca`; reject the
+        // combination up front rather than silently falling back to no client auth.
+        if matches.get_flag("require-client-cert") && matches.get_one::<String>("client-ca").is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "--require-client-cert requires --client-ca to be set",
+            ));
+        }
+
+        // If a client CA bundle was provided, build a client certificate verifier so the
+        // server can authenticate clients in addition to clients authenticating the server.
+        let client_cert_verifier: Option<std::sync::Arc<dyn rustls::server::ClientCertVerifier>> =
+            if let Some(client_ca_path) = matches.get_one::<String>("client-ca") {
+                let ca_file = std::fs::File::open(client_ca_path)?;
+                let mut ca_reader = std::io::BufReader::new(ca_file);
+                let mut client_ca_store = RootCertStore::empty();
+                for der in certs(&mut ca_reader).filter_map(Result::ok) {
+                    client_ca_store
+                        .add(&Certificate(der.deref().to_vec()))
+                        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid client CA certificate"))?;
+                }
+
+                if matches.get_flag("require-client-cert") {
+                    Some(AllowAnyAuthenticatedClient::new(client_ca_store).boxed())
+                } else {
+                    Some(AllowAnyAnonymousOrAuthenticatedClient::new(client_ca_store).boxed())
+                }
+            } else {
+                None
+            };
+
         // Create a new server configuration with the certificate and key
-        let config = ServerConfig::builder()
-            .with_safe_defaults()
-            .with_no_client_auth()
+        let config_builder = ServerConfig::builder().with_safe_defaults();
+        let config = match client_cert_verifier {
+            Some(verifier) => config_builder.with_client_cert_verifier(verifier),
+            None => config_builder.with_no_client_auth(),
+        }
             .with_single_cert(cert_chain, keys.remove(0))
             .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid certificate or key"))?;
+
+        // Advertise HTTP/2 alongside HTTP/1.1 via ALPN so capable clients negotiate the
+        // more efficient protocol automatically while older clients keep working.
+        let mut config = config;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        // Opt-in TLS key logging for debugging: the key logger is installed when either the
+        // --keylog flag or the SSLKEYLOGFILE environment variable is present. `KeyLogFile` only
+        // writes when SSLKEYLOGFILE names a file, so --keylog on its own logs nothing.
+        if matches.get_flag("keylog") || std::env::var_os("SSLKEYLOGFILE").is_some() {
+            config.key_log = std::sync::Arc::new(rustls::KeyLogFile::new());
+        }
     
         // Bind the server to the address with the configuration
         println!("Listening on https://{}", bind_address);